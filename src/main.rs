@@ -1,21 +1,24 @@
 //! Simple utility for picking OpenSSH shell on Windows after establishing SSH connection.
 
+use clap::{Parser, Subcommand};
+use command_group::CommandGroup;
 use cursive::align::HAlign;
 use cursive::style::gradient::Linear;
 use cursive::style::Rgb;
 use cursive::utils::markup::gradient;
 use cursive::view::Nameable;
-use cursive::views::{Dialog, LinearLayout, SelectView, TextView};
+use cursive::views::{Dialog, EditView, LinearLayout, SelectView, TextView};
 use cursive::CursiveRunnable;
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::env;
 use std::fs;
 use std::fs::File;
-use std::path::Path;
-use std::process::Command;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -28,12 +31,21 @@ const PROGRAM_DATA: &str = "%SystemDrive%\\ProgramData\\dzonder\\SSHells";
 /// Base name of the configuration file.
 const CONFIG: &str = "config.json";
 
+/// Default configuration, embedded at compile time and written on first run.
+const DEFAULT_CONFIG: &str = include_str!("config.json");
+
+/// Base name of the persisted state file.
+const STATE: &str = "state.json";
+
 /// Index of the default shell in the list of shells.
 const DEFAULT_SHELL_INDEX: usize = 0;
 
 /// Timeout for executing the default shell.
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
 
+/// Timeout for a single detection probe command.
+const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// Stores information about a shell (e.g. its name and how it should be executed).
 #[derive(Deserialize)]
 struct Sshell {
@@ -43,26 +55,110 @@ struct Sshell {
     #[serde(default)]
     args: Vec<String>,
 
+    /// Working directory to run the shell in.
+    #[serde(default)]
+    cwd: Option<String>,
+
+    /// Extra environment variables to set for the shell, as `(name, value)` pairs.
+    #[serde(default)]
+    env: Vec<(String, String)>,
+
+    /// Per-shell countdown timeout (in seconds), overriding the global default.
+    #[serde(default)]
+    timeout: Option<u64>,
+
+    /// Optional probe that enumerates shells dynamically, one per stdout line.
+    #[serde(default)]
+    detect: Option<Detect>,
+
     #[serde(skip)]
     expanded_path: String,
+
+    #[serde(skip)]
+    expanded_cwd: Option<String>,
+
+    #[serde(skip)]
+    expanded_env: Vec<(String, String)>,
+
+    #[serde(skip)]
+    clear: Option<Arc<clearscreen::ClearScreen>>,
 }
 
 impl Sshell {
     /// Run the selected shell. Exits after shell terminates.
-    fn run(&self) {
-        // Reset colors, clear the terminal screen and move cursor.
-        print!("\x1B[0m\x1B[?25h\x1B[2J\x1B[1;1H");
-        Command::new(&self.expanded_path)
-            .args(&self.args)
-            .spawn()
-            .expect("shell failed to start");
-        std::process::exit(0);
+    fn run(&self) -> ! {
+        // Reset and clear the terminal in a terminfo-appropriate way, so it
+        // behaves across the varied clients that connect over SSH.
+        let _ = match &self.clear {
+            Some(clear) => clear.clear(),
+            None => clearscreen::clear(),
+        };
+        let mut command = Command::new(&self.expanded_path);
+        command.args(&self.args);
+        if let Some(cwd) = &self.expanded_cwd {
+            command.current_dir(cwd);
+        }
+        command.envs(self.expanded_env.iter().map(|(k, v)| (k, v)));
+        // Own the whole child process group so it is cleaned up with us (e.g. when
+        // the SSH connection drops), then propagate its real exit status.
+        let status = command
+            .group_spawn()
+            .expect("shell failed to start")
+            .wait()
+            .expect("failed to wait for shell");
+        std::process::exit(status.code().unwrap_or(1));
     }
 
     /// Checks if this shell exists in the system.
     fn exists(&self) -> bool {
         Path::new(&self.expanded_path).exists()
     }
+
+    /// Build a concrete shell from this detection template for a single probed
+    /// `line`. The line is substituted for any `{}` placeholder in the args (or
+    /// appended when none is present); all other execution options are inherited.
+    fn discovered(&self, line: &str) -> Sshell {
+        let mut args = Vec::with_capacity(self.args.len());
+        let mut replaced = false;
+        for arg in &self.args {
+            let expanded = expand_env_vars(arg).into_owned();
+            if expanded.contains("{}") {
+                replaced = true;
+                args.push(expanded.replace("{}", line));
+            } else {
+                args.push(expanded);
+            }
+        }
+        if !replaced {
+            args.push(line.to_string());
+        }
+        Sshell {
+            name: line.to_string(),
+            path: self.path.clone(),
+            args,
+            cwd: self.cwd.clone(),
+            env: self.env.clone(),
+            timeout: self.timeout,
+            detect: None,
+            expanded_path: self.expanded_path.clone(),
+            expanded_cwd: self.expanded_cwd.clone(),
+            expanded_env: self.expanded_env.clone(),
+            clear: self.clear.clone(),
+        }
+    }
+}
+
+/// Probe command that enumerates installed shells at runtime (e.g. `wsl.exe -l -q`).
+#[derive(Deserialize)]
+struct Detect {
+    command: String,
+
+    #[serde(default)]
+    args: Vec<String>,
+
+    /// Probe timeout (in seconds), overriding [`DEFAULT_PROBE_TIMEOUT`].
+    #[serde(default)]
+    timeout: Option<u64>,
 }
 
 /// Expand environmental variables (e.g. `%SystemRoot%`) in a path string.
@@ -71,28 +167,247 @@ fn expand_env_vars(path: &str) -> Cow<'_, str> {
         static ref ENV_VAR_REGEX: Regex = Regex::new("%([[:word:]]+)%").unwrap();
     }
     ENV_VAR_REGEX.replace_all(path, |c: &Captures| {
-        env::var(&c[1]).expect("invalid environmental variable")
+        env::var(&c[1]).unwrap_or_else(|_| {
+            // Leave the reference intact rather than panicking, so a single bad
+            // entry does not take down the whole picker.
+            eprintln!("warning: skipping unknown environmental variable {:?}", &c[1]);
+            c[0].to_string()
+        })
     })
 }
 
-/// Read and parse list of shells from a configuration file.
-fn read_config() -> Vec<Sshell> {
-    let program_data: String = expand_env_vars(PROGRAM_DATA).into();
-    let cfg_dir = Path::new(&program_data);
-    let cfg_path = cfg_dir.join(CONFIG);
+/// Parse a clearscreen strategy name from the config, falling back to the
+/// autodetecting default for unknown values.
+fn parse_clearscreen(name: Option<&str>) -> clearscreen::ClearScreen {
+    use clearscreen::ClearScreen;
+    match name {
+        None | Some("default") => ClearScreen::default(),
+        Some("terminfo") => ClearScreen::Terminfo,
+        Some("xterm-clear") => ClearScreen::XtermClear,
+        Some("windows-vt") => ClearScreen::WindowsVt,
+        Some("vt-leave-alt") => ClearScreen::VtLeaveAlt,
+        Some("vt-ris") => ClearScreen::VtRis,
+        Some(other) => {
+            eprintln!("warning: unknown clearscreen strategy {other:?}, using default");
+            ClearScreen::default()
+        }
+    }
+}
+
+/// On-disk configuration, accepted either as a bare array of shells or as an
+/// object carrying global knobs alongside the `shells` array.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawConfig {
+    Shells(Vec<Sshell>),
+    Object {
+        /// Strategy used to reset/clear the terminal before launching a shell.
+        #[serde(default)]
+        clearscreen: Option<String>,
+        shells: Vec<Sshell>,
+    },
+}
+
+/// Resolve the configuration file path, honouring a `--config` override.
+fn resolve_config_path(config_override: Option<&str>) -> PathBuf {
+    match config_override {
+        Some(path) => PathBuf::from(expand_env_vars(path).into_owned()),
+        None => {
+            let program_data: String = expand_env_vars(PROGRAM_DATA).into();
+            Path::new(&program_data).join(CONFIG)
+        }
+    }
+}
+
+/// Write the default configuration to `cfg_path` if it does not already exist.
+///
+/// Returns `Ok(true)` when a fresh config was written, `Ok(false)` when one was
+/// already present.
+fn write_default_config(cfg_path: &Path) -> std::io::Result<bool> {
+    if cfg_path.exists() {
+        return Ok(false);
+    }
+    if let Some(cfg_dir) = cfg_path.parent() {
+        fs::create_dir_all(cfg_dir)?;
+    }
+    fs::write(cfg_path, DEFAULT_CONFIG)?;
+    Ok(true)
+}
+
+/// Run a detection probe with a bounded timeout, returning its non-empty,
+/// trimmed stdout lines. Any failure is warned about and yields no entries.
+fn run_probe(command: &str, args: &[String], timeout: Duration) -> Vec<String> {
+    let mut child = match Command::new(command)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            eprintln!("warning: detection probe {command:?} failed to start: {err}");
+            return Vec::new();
+        }
+    };
+
+    // Drain stdout on a separate thread so a probe writing more than the pipe
+    // buffer can't block on its write and be mistaken for a timeout.
+    let reader = child.stdout.take().map(|mut stdout| {
+        std::thread::spawn(move || {
+            // Read raw bytes and decode lossily: some probes (e.g. `wsl.exe -l -q`)
+            // emit non-UTF-8 output, which `read_to_string` would reject wholesale.
+            let mut buf = Vec::new();
+            let _ = stdout.read_to_end(&mut buf);
+            buf
+        })
+    });
+
+    let start = Instant::now();
+    let succeeded = loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => break true,
+            Ok(Some(status)) => {
+                eprintln!("warning: detection probe {command:?} exited with {status}");
+                break false;
+            }
+            Ok(None) if start.elapsed() >= timeout => {
+                eprintln!("warning: detection probe {command:?} timed out");
+                let _ = child.kill();
+                break false;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(err) => {
+                eprintln!("warning: detection probe {command:?} failed: {err}");
+                let _ = child.kill();
+                break false;
+            }
+        }
+    };
+    let _ = child.wait();
+    if !succeeded {
+        return Vec::new();
+    }
+
+    let bytes = reader.and_then(|handle| handle.join().ok()).unwrap_or_default();
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read and parse list of shells from a configuration file, returning a
+/// diagnostic string on any I/O or parse error rather than panicking.
+fn read_config(cfg_path: &Path) -> Result<Vec<Sshell>, String> {
     // Write default config if none exists.
-    if !cfg_path.exists() {
-        let _ = fs::create_dir_all(cfg_dir);
-        fs::write(&cfg_path, include_str!("config.json")).expect("failed to write default config");
-    }
-    let cfg = File::open(cfg_path).expect("failed to open config file");
-    let mut sshells: Vec<Sshell> =
-        serde_json::from_reader(cfg).expect("failed to parse config file");
-    // Expand environmental variables in all paths.
-    for sshell in sshells.iter_mut() {
+    write_default_config(cfg_path)
+        .map_err(|e| format!("failed to write default config to {}: {e}", cfg_path.display()))?;
+    let cfg = File::open(cfg_path)
+        .map_err(|e| format!("failed to open config file {}: {e}", cfg_path.display()))?;
+    let raw: RawConfig = serde_json::from_reader(cfg)
+        .map_err(|e| format!("failed to parse config file {}: {e}", cfg_path.display()))?;
+    let (mut shells, clearscreen) = match raw {
+        RawConfig::Shells(shells) => (shells, None),
+        RawConfig::Object {
+            clearscreen,
+            shells,
+        } => (shells, clearscreen),
+    };
+    let clear = Arc::new(parse_clearscreen(clearscreen.as_deref()));
+    // Expand environmental variables in all paths, working directories and env values.
+    for sshell in shells.iter_mut() {
         sshell.expanded_path = expand_env_vars(&sshell.path).into();
+        sshell.expanded_cwd = sshell
+            .cwd
+            .as_ref()
+            .map(|cwd| expand_env_vars(cwd).into_owned());
+        sshell.expanded_env = sshell
+            .env
+            .iter()
+            .map(|(k, v)| (k.clone(), expand_env_vars(v).into_owned()))
+            .collect();
+        sshell.clear = Some(clear.clone());
     }
+    // Expand static entries as-is and replace any detection templates with the
+    // shells their probe discovers.
+    let mut resolved = Vec::with_capacity(shells.len());
+    for sshell in shells {
+        match &sshell.detect {
+            Some(detect) => {
+                let timeout = detect
+                    .timeout
+                    .map_or(DEFAULT_PROBE_TIMEOUT, Duration::from_secs);
+                let command = expand_env_vars(&detect.command).into_owned();
+                let probe_args: Vec<String> = detect
+                    .args
+                    .iter()
+                    .map(|arg| expand_env_vars(arg).into_owned())
+                    .collect();
+                for line in run_probe(&command, &probe_args, timeout) {
+                    resolved.push(sshell.discovered(&line));
+                }
+            }
+            None => resolved.push(sshell),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Persisted state, remembering the most recent selection across sessions.
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    /// Name of the shell the user last submitted.
+    last_shell: Option<String>,
+}
+
+/// Resolve the state file path, stored next to the configuration file.
+fn resolve_state_path(cfg_path: &Path) -> PathBuf {
+    cfg_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(STATE)
+}
+
+/// Read the persisted state, returning the default on any error.
+fn read_state(state_path: &Path) -> State {
+    File::open(state_path)
+        .ok()
+        .and_then(|f| serde_json::from_reader(f).ok())
+        .unwrap_or_default()
+}
+
+/// Persist the state, silently ignoring write errors.
+fn write_state(state_path: &Path, state: &State) {
+    if let Ok(json) = serde_json::to_string_pretty(state) {
+        let _ = fs::write(state_path, json);
+    }
+}
+
+/// Resolve the index of the shell to preselect: the remembered one if it still
+/// exists, otherwise the first shell that exists (or [`DEFAULT_SHELL_INDEX`] if
+/// none do).
+fn default_shell_index(sshells: &[Sshell], state: &State) -> usize {
+    state
+        .last_shell
+        .as_deref()
+        .and_then(|name| {
+            sshells
+                .iter()
+                .position(|s| s.name == name && s.exists())
+        })
+        .or_else(|| sshells.iter().position(Sshell::exists))
+        .unwrap_or(DEFAULT_SHELL_INDEX)
+}
+
+/// View position of `default_index` within the SelectView, which only holds the
+/// shells that [`Sshell::exists`].
+fn default_view_position(sshells: &[Sshell], default_index: usize) -> usize {
     sshells
+        .iter()
+        .take(default_index)
+        .filter(|s| s.exists())
+        .count()
 }
 
 /// State of the countdown timer.
@@ -105,6 +420,9 @@ struct TimerState {
 fn sshells_select(
     sshells: Arc<Vec<Sshell>>,
     timer_state: Arc<Mutex<TimerState>>,
+    default_index: usize,
+    default_position: usize,
+    state_path: Arc<PathBuf>,
 ) -> SelectView<usize> {
     let mut select_view = SelectView::new().autojump();
     for (i, sshell) in sshells.iter().enumerate() {
@@ -112,16 +430,24 @@ fn sshells_select(
             select_view.add_item(sshell.name.clone(), i);
         }
     }
-    select_view.set_selection(0);
+    select_view.set_selection(default_position);
     let sshells_clone = sshells.clone();
     select_view.set_on_select(move |s, _| {
         let mut timer = timer_state.lock().unwrap();
         if timer.active {
             timer.active = false;
-            set_shell_label(s, DEFAULT_SHELL_INDEX, &sshells[DEFAULT_SHELL_INDEX].name);
+            set_shell_label(s, default_position, &sshells[default_index].name);
         }
     });
-    select_view.set_on_submit(move |_, &index| sshells_clone[index].run());
+    select_view.set_on_submit(move |_, &index| {
+        write_state(
+            &state_path,
+            &State {
+                last_shell: Some(sshells_clone[index].name.clone()),
+            },
+        );
+        sshells_clone[index].run()
+    });
     select_view
 }
 
@@ -130,6 +456,8 @@ fn handle_timer_tick(
     s: &mut cursive::Cursive,
     sshells: &[Sshell],
     timer_state: &Arc<Mutex<TimerState>>,
+    default_index: usize,
+    default_position: usize,
 ) {
     let mut timer = timer_state.lock().unwrap();
     if !timer.active {
@@ -140,18 +468,28 @@ fn handle_timer_tick(
     if now >= timer.end_time {
         timer.active = false;
         s.quit();
-        sshells[DEFAULT_SHELL_INDEX].run();
+        sshells[default_index].run();
     } else {
         let remaining = timer.end_time - now;
         let label = format!(
             "{} ({})",
-            sshells[DEFAULT_SHELL_INDEX].name,
+            sshells[default_index].name,
             remaining.as_secs() + 1
         );
-        set_shell_label(s, DEFAULT_SHELL_INDEX, &label);
+        set_shell_label(s, default_position, &label);
     }
 }
 
+/// Fuzzy substring match: are the characters of `query` present in `name`, in
+/// order, ignoring ASCII case? An empty query matches everything.
+fn fuzzy_match(name: &str, query: &str) -> bool {
+    let mut haystack = name.chars().map(|c| c.to_ascii_lowercase());
+    query
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .all(|needle| haystack.any(|c| c == needle))
+}
+
 /// Update the label of a shell in the SelectView.
 fn set_shell_label(s: &mut cursive::Cursive, index: usize, label: &str) {
     if let Some(mut select) = s.find_name::<SelectView<usize>>("select") {
@@ -162,9 +500,20 @@ fn set_shell_label(s: &mut cursive::Cursive, index: usize, label: &str) {
 }
 
 /// Set up the cursive TUI environment.
-fn setup_tui(sshells: Arc<Vec<Sshell>>) -> CursiveRunnable {
+fn setup_tui(
+    sshells: Arc<Vec<Sshell>>,
+    cli_timeout: Option<u64>,
+    default_index: usize,
+    state_path: Arc<PathBuf>,
+) -> CursiveRunnable {
+    // An explicit `--timeout` wins; otherwise fall back to the default shell's
+    // own timeout, then to the global default.
+    let timeout = cli_timeout
+        .or(sshells[default_index].timeout)
+        .map_or(DEFAULT_TIMEOUT, Duration::from_secs);
+    let default_position = default_view_position(&sshells, default_index);
     let timer_state = Arc::new(Mutex::new(TimerState {
-        end_time: Instant::now() + DEFAULT_TIMEOUT,
+        end_time: Instant::now() + timeout,
         active: true,
     }));
 
@@ -174,7 +523,7 @@ fn setup_tui(sshells: Arc<Vec<Sshell>>) -> CursiveRunnable {
     let timer_clone = timer_state.clone();
     let sshells_clone = sshells.clone();
     siv.set_on_pre_event(cursive::event::Event::Refresh, move |s| {
-        handle_timer_tick(s, &sshells_clone, &timer_clone);
+        handle_timer_tick(s, &sshells_clone, &timer_clone, default_index, default_position);
     });
 
     let version_text = gradient::decorate_back(
@@ -182,22 +531,145 @@ fn setup_tui(sshells: Arc<Vec<Sshell>>) -> CursiveRunnable {
         Linear::simple(Rgb::yellow(), Rgb::cyan()),
     );
     siv.add_global_callback('q', |s| s.quit());
+
+    // Search box that narrows the list as the user types; filtering cancels the
+    // countdown the same way selecting an entry does.
+    let filter_sshells = sshells.clone();
+    let filter_timer = timer_state.clone();
+    let search = EditView::new().on_edit(move |s, query, _| {
+        filter_timer.lock().unwrap().active = false;
+        if let Some(mut select) = s.find_name::<SelectView<usize>>("select") {
+            select.clear();
+            for (i, sshell) in filter_sshells.iter().enumerate() {
+                if sshell.exists() && fuzzy_match(&sshell.name, query) {
+                    select.add_item(sshell.name.clone(), i);
+                }
+            }
+        }
+    });
+
     siv.add_layer(
         LinearLayout::vertical()
             .child(TextView::new(version_text).h_align(HAlign::Center))
+            .child(search)
             .child(Dialog::around(
-                sshells_select(sshells, timer_state).with_name("select"),
+                sshells_select(sshells, timer_state, default_index, default_position, state_path)
+                    .with_name("select"),
             )),
     );
     siv
 }
 
-/// Reads the configuration and sets up the select view in a TUI.
-fn main() {
-    let sshells: Arc<Vec<Sshell>> = Arc::new(read_config());
+/// Command-line interface for non-interactive use (scripting, `ForceCommand`, CI).
+#[derive(Parser)]
+#[command(name = "sshells", version, about = "Pick an OpenSSH shell after connecting over SSH.")]
+struct Cli {
+    /// Override the path to the configuration file.
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<String>,
+
+    /// Override the countdown timeout (in seconds) before the default shell runs.
+    #[arg(long, global = true, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+/// Non-interactive subcommands. Absent means "launch the TUI".
+#[derive(Subcommand)]
+enum Commands {
+    /// Print the embedded default configuration to stdout.
+    PrintDefaultConfig,
+    /// List the names of configured shells that exist on this system.
+    List,
+    /// Expand and exec a configured shell directly by name, bypassing the TUI.
+    Run {
+        /// Name of the shell to run, as it appears in the configuration.
+        name: String,
+    },
+    /// Write the default configuration if missing and print setup guidance.
+    Install,
+}
+
+/// Launch the interactive picker.
+fn run_tui(sshells: Vec<Sshell>, cli_timeout: Option<u64>, state_path: PathBuf) -> ExitCode {
+    let sshells = Arc::new(sshells);
     if sshells.is_empty() {
         println!("No shells configured or configuration file not found.");
-        return;
+        return ExitCode::SUCCESS;
+    }
+    let default_index = default_shell_index(&sshells, &read_state(&state_path));
+    setup_tui(sshells, cli_timeout, default_index, Arc::new(state_path)).run();
+    ExitCode::SUCCESS
+}
+
+/// Parses arguments and dispatches to a subcommand or the interactive picker.
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let cfg_path = resolve_config_path(cli.config.as_deref());
+
+    match cli.command {
+        Some(Commands::PrintDefaultConfig) => {
+            print!("{DEFAULT_CONFIG}");
+            ExitCode::SUCCESS
+        }
+        Some(Commands::List) => match read_config(&cfg_path) {
+            Ok(shells) => {
+                for sshell in shells.iter().filter(|s| s.exists()) {
+                    println!("{}", sshell.name);
+                }
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+        },
+        Some(Commands::Run { name }) => match read_config(&cfg_path) {
+            Ok(shells) => match shells.into_iter().find(|s| s.name == name) {
+                Some(sshell) if sshell.exists() => sshell.run(),
+                Some(_) => {
+                    eprintln!("shell {name:?} is configured but not present on this system");
+                    ExitCode::FAILURE
+                }
+                None => {
+                    eprintln!("no shell named {name:?} in configuration");
+                    ExitCode::FAILURE
+                }
+            },
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+        },
+        Some(Commands::Install) => match write_default_config(&cfg_path) {
+            Ok(written) => {
+                if written {
+                    println!("Wrote default configuration to {}", cfg_path.display());
+                } else {
+                    println!("Configuration already present at {}", cfg_path.display());
+                }
+                println!(
+                    "Add `ForceCommand sshells` to your sshd_config (or a `command=` \
+                     prefix in authorized_keys) to launch the picker on login."
+                );
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("failed to write configuration to {}: {err}", cfg_path.display());
+                ExitCode::FAILURE
+            }
+        },
+        None => {
+            let state_path = resolve_state_path(&cfg_path);
+            match read_config(&cfg_path) {
+                Ok(shells) => run_tui(shells, cli.timeout, state_path),
+                Err(err) => {
+                    eprintln!("{err}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
     }
-    setup_tui(sshells).run();
 }